@@ -0,0 +1,38 @@
+//! Public client for querying weapon statistics.
+
+use crate::database::manager::{EffectiveDamageRow, TimeToKillRow};
+use crate::database::DatabaseManager;
+use crate::models::DatabaseConfig;
+use crate::Result;
+use futures_util::stream::BoxStream;
+
+/// High-level entry point for consumers of this library: connects to the
+/// database and exposes DB-computed query methods so callers never have to
+/// re-derive damage or time-to-kill math themselves.
+pub struct StatsClient {
+    manager: DatabaseManager,
+}
+
+impl StatsClient {
+    /// Connect to the database described by `config`.
+    pub async fn connect(config: &DatabaseConfig) -> Result<Self> {
+        Ok(Self { manager: DatabaseManager::new(config).await? })
+    }
+
+    /// Access the underlying `DatabaseManager` for schema/admin operations
+    /// (migrations, sync, audit logging) that aren't part of the query API.
+    pub fn manager(&self) -> &DatabaseManager {
+        &self.manager
+    }
+
+    /// Stream `config_effective_damage` rows, optionally filtered to a
+    /// single configuration.
+    pub fn effective_damage<'a>(&'a self, config_id: Option<i32>) -> BoxStream<'a, Result<EffectiveDamageRow>> {
+        self.manager.stream_effective_damage(config_id)
+    }
+
+    /// Stream `config_ttk` rows, optionally filtered to a single configuration.
+    pub fn time_to_kill<'a>(&'a self, config_id: Option<i32>) -> BoxStream<'a, Result<TimeToKillRow>> {
+        self.manager.stream_ttk(config_id)
+    }
+}
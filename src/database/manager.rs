@@ -3,20 +3,246 @@
 use crate::models::{DatabaseConfig, ValidationReport};
 use crate::{Result, StatsError};
 use sqlx::PgPool;
+use std::time::Duration;
 use tracing::{debug, info};
 
+/// Per-connection session tuning applied via `after_connect` to every
+/// connection the pool opens, so operators can cap runaway queries and tag
+/// connections for observability without hand-tuning each call site.
+/// Carried on `DatabaseConfig::connection_options` and read by
+/// `DatabaseManager::new`.
+#[derive(Debug, Clone, Default)]
+pub struct ConnectionOptions {
+    /// `SET statement_timeout = ...` — aborts queries that run longer than this.
+    pub statement_timeout: Option<Duration>,
+    /// `SET lock_timeout = ...` — aborts waits for a contended lock.
+    pub lock_timeout: Option<Duration>,
+    /// `SET application_name = ...` — identifies this pool's connections in `pg_stat_activity`.
+    pub application_name: Option<String>,
+    /// `SET search_path = ...` — isolates this library's tables into a dedicated schema.
+    pub search_path: Option<String>,
+}
+
+/// A single versioned migration: a forward (`up`) script and the reverse
+/// (`down`) script that undoes it. Versions are applied in ascending order
+/// and rolled back in descending order.
+struct Migration {
+    version: i32,
+    name: &'static str,
+    up: &'static str,
+    down: &'static str,
+}
+
+/// Embedded migrations, in ascending version order. Migration 0001 is the
+/// schema that `create_schema` used to issue directly; later migrations
+/// must only ever be appended, never edited in place, once released.
+const MIGRATIONS: &[Migration] = &[Migration {
+    version: 1,
+    name: "initial_schema",
+    up: r#"
+        CREATE TABLE IF NOT EXISTS categories (
+            category_id SERIAL PRIMARY KEY,
+            category_name VARCHAR(50) NOT NULL UNIQUE
+        );
+
+        CREATE TABLE IF NOT EXISTS weapons (
+            weapon_id SERIAL PRIMARY KEY,
+            weapon_name VARCHAR(100) NOT NULL UNIQUE,
+            category_id INTEGER NOT NULL REFERENCES categories(category_id)
+        );
+
+        CREATE TABLE IF NOT EXISTS barrels (
+            barrel_id SERIAL PRIMARY KEY,
+            barrel_name VARCHAR(100) NOT NULL UNIQUE
+        );
+
+        CREATE TABLE IF NOT EXISTS ammo_types (
+            ammo_id SERIAL PRIMARY KEY,
+            ammo_type_name VARCHAR(100) NOT NULL UNIQUE
+        );
+
+        CREATE TABLE IF NOT EXISTS weapon_ammo_stats (
+            weapon_id INTEGER NOT NULL REFERENCES weapons(weapon_id),
+            ammo_id INTEGER NOT NULL REFERENCES ammo_types(ammo_id),
+            magazine_size SMALLINT NOT NULL,
+            empty_reload_time DECIMAL(4,2),
+            tactical_reload_time DECIMAL(4,2),
+            headshot_multiplier DECIMAL(3,1) NOT NULL,
+            pellet_count SMALLINT DEFAULT 1,
+            PRIMARY KEY (weapon_id, ammo_id)
+        );
+
+        CREATE TABLE IF NOT EXISTS configurations (
+            config_id SERIAL PRIMARY KEY,
+            weapon_id INTEGER NOT NULL REFERENCES weapons(weapon_id),
+            barrel_id INTEGER NOT NULL REFERENCES barrels(barrel_id),
+            ammo_id INTEGER NOT NULL REFERENCES ammo_types(ammo_id),
+            velocity SMALLINT NOT NULL,
+            rpm_single SMALLINT,
+            rpm_burst SMALLINT,
+            rpm_auto SMALLINT,
+            UNIQUE(weapon_id, barrel_id, ammo_id)
+        );
+
+        CREATE TABLE IF NOT EXISTS config_dropoffs (
+            config_id INTEGER NOT NULL REFERENCES configurations(config_id),
+            range SMALLINT NOT NULL,
+            damage DECIMAL(5,1) NOT NULL,
+            PRIMARY KEY (config_id, range)
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_weapons_category ON weapons(category_id);
+        CREATE INDEX IF NOT EXISTS idx_configurations_weapon ON configurations(weapon_id);
+        CREATE INDEX IF NOT EXISTS idx_config_dropoffs_config ON config_dropoffs(config_id);
+        CREATE INDEX IF NOT EXISTS idx_config_dropoffs_range ON config_dropoffs(range);
+        CREATE INDEX IF NOT EXISTS idx_weapon_ammo_stats_weapon ON weapon_ammo_stats(weapon_id);
+    "#,
+    down: r#"
+        DROP TABLE IF EXISTS config_dropoffs CASCADE;
+        DROP TABLE IF EXISTS configurations CASCADE;
+        DROP TABLE IF EXISTS weapon_ammo_stats CASCADE;
+        DROP TABLE IF EXISTS weapons CASCADE;
+        DROP TABLE IF EXISTS ammo_types CASCADE;
+        DROP TABLE IF EXISTS barrels CASCADE;
+        DROP TABLE IF EXISTS categories CASCADE;
+    "#,
+}];
+
+/// One applied row of the `schema_migrations` tracking table.
+#[derive(Debug, Clone)]
+pub struct AppliedMigration {
+    pub version: i32,
+    pub name: String,
+    pub applied_at: String,
+}
+
+/// Result of `migration_status`: what has been applied versus what is
+/// still pending against the embedded `MIGRATIONS` list.
+#[derive(Debug, Clone)]
+pub struct MigrationStatus {
+    pub applied: Vec<AppliedMigration>,
+    pub pending: Vec<(i32, String)>,
+}
+
+/// Insert/update/delete counts for a single table touched by `sync_from_json`.
+/// `updated` counts every existing row the upsert matched, whether or not
+/// any of its values changed.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TableSyncStats {
+    pub inserted: i64,
+    pub updated: i64,
+    pub deleted: i64,
+}
+
+/// Per-table reconciliation counts returned by `sync_from_json`, so callers
+/// can log how the incoming data was reconciled with the previous data.
+#[derive(Debug, Clone, Default)]
+pub struct SyncSummary {
+    pub categories: TableSyncStats,
+    pub barrels: TableSyncStats,
+    pub ammo_types: TableSyncStats,
+    pub weapons: TableSyncStats,
+    pub weapon_ammo_stats: TableSyncStats,
+    pub configurations: TableSyncStats,
+    pub config_dropoffs: TableSyncStats,
+}
+
+/// The operation that produced a `config_dropoffs_history` row.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HistoryOperation {
+    Update,
+    Delete,
+}
+
+/// A single prior version of a `config_dropoffs` row, as captured by the
+/// `config_dropoffs_history` audit trigger.
+#[derive(Debug, Clone)]
+pub struct DropoffHistoryEntry {
+    pub config_id: i32,
+    pub range: i16,
+    pub damage: f64,
+    pub operation: HistoryOperation,
+    pub changed_at: String,
+}
+
+/// A row of the `config_effective_damage` view: per-range damage for a
+/// configuration, with headshot and per-pellet/total variants coalesced
+/// server-side so consumers never re-derive the formula.
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct EffectiveDamageRow {
+    pub config_id: i32,
+    pub weapon_id: i32,
+    pub barrel_id: i32,
+    pub ammo_id: i32,
+    pub range: i16,
+    pub damage_per_pellet: f64,
+    pub damage_total: f64,
+    pub headshot_damage_per_pellet: f64,
+    pub headshot_damage_total: f64,
+}
+
+/// A row of the `config_ttk` view: shots-to-kill and time-to-kill against
+/// a 100-HP target at a given range, using the configuration's highest
+/// available fire rate.
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct TimeToKillRow {
+    pub config_id: i32,
+    pub weapon_id: i32,
+    pub barrel_id: i32,
+    pub ammo_id: i32,
+    pub range: i16,
+    pub damage_total: f64,
+    pub shots_to_kill: f64,
+    pub ttk_ms: f64,
+}
+
 /// Manages database connections and operations
 pub struct DatabaseManager {
     pool: PgPool,
 }
 
 impl DatabaseManager {
-    /// Create a new database manager with the given configuration
+    /// Create a new database manager with the given configuration, applying
+    /// `config.connection_options` to every connection the pool opens.
     pub async fn new(config: &DatabaseConfig) -> Result<Self> {
         info!("Connecting to database: {}", config.url());
 
+        let options = config.connection_options.clone();
         let pool = sqlx::postgres::PgPoolOptions::new()
             .max_connections(config.max_connections)
+            .after_connect(move |conn, _meta| {
+                let options = options.clone();
+                Box::pin(async move {
+                    // set_config() takes its value as a bind parameter, so
+                    // operator-supplied strings can't break out of the
+                    // statement the way string-interpolated SET would.
+                    if let Some(timeout) = options.statement_timeout {
+                        sqlx::query("SELECT set_config('statement_timeout', $1, false)")
+                            .bind(timeout.as_millis().to_string())
+                            .execute(&mut *conn)
+                            .await?;
+                    }
+                    if let Some(timeout) = options.lock_timeout {
+                        sqlx::query("SELECT set_config('lock_timeout', $1, false)")
+                            .bind(timeout.as_millis().to_string())
+                            .execute(&mut *conn)
+                            .await?;
+                    }
+                    if let Some(ref name) = options.application_name {
+                        sqlx::query("SELECT set_config('application_name', $1, false)")
+                            .bind(name)
+                            .execute(&mut *conn)
+                            .await?;
+                    }
+                    if let Some(ref path) = options.search_path {
+                        sqlx::query("SELECT set_config('search_path', $1, false)")
+                            .bind(path)
+                            .execute(&mut *conn)
+                            .await?;
+                    }
+                    Ok(())
+                })
+            })
             .connect(config.url())
             .await?;
 
@@ -38,107 +264,401 @@ impl DatabaseManager {
         Ok(())
     }
 
-    /// Create the database schema
+    /// Create the database schema by applying all pending migrations,
+    /// starting from migration 0001 (`initial_schema`).
     pub async fn create_schema(&self) -> Result<()> {
         info!("Creating database schema");
+        self.migrate().await
+    }
 
-        // Execute schema statements in order
-        let schema_statements = [
-            // Categories table
+    /// Ensure the `schema_migrations` tracking table exists.
+    async fn ensure_migrations_table(&self) -> Result<()> {
+        sqlx::query(
             r#"
-            CREATE TABLE IF NOT EXISTS categories (
-                category_id SERIAL PRIMARY KEY,
-                category_name VARCHAR(50) NOT NULL UNIQUE
+            CREATE TABLE IF NOT EXISTS schema_migrations (
+                version INTEGER PRIMARY KEY,
+                name VARCHAR(200) NOT NULL,
+                applied_at TIMESTAMPTZ NOT NULL DEFAULT now()
             )
             "#,
-            
-            // Weapons table
-            r#"
-            CREATE TABLE IF NOT EXISTS weapons (
-                weapon_id SERIAL PRIMARY KEY,
-                weapon_name VARCHAR(100) NOT NULL UNIQUE,
-                category_id INTEGER NOT NULL REFERENCES categories(category_id)
-            )
-            "#,
-            
-            // Barrels table
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Fetch the set of already-applied migration versions.
+    async fn applied_versions(&self) -> Result<std::collections::HashSet<i32>> {
+        let rows: Vec<(i32,)> = sqlx::query_as("SELECT version FROM schema_migrations")
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows.into_iter().map(|(version,)| version).collect())
+    }
+
+    /// Apply all pending migrations in ascending version order. Each
+    /// migration runs inside its own transaction, so a failing migration
+    /// leaves the database at the last successfully applied version
+    /// instead of a half-migrated state.
+    pub async fn migrate(&self) -> Result<()> {
+        self.ensure_migrations_table().await?;
+        let applied = self.applied_versions().await?;
+
+        for migration in MIGRATIONS {
+            if applied.contains(&migration.version) {
+                continue;
+            }
+
+            info!("Applying migration {:04}_{}", migration.version, migration.name);
+
+            // Scripts hold several statements, so they go through the simple
+            // query protocol (`Executor::execute` on a `&str`), not a prepared one.
+            let mut tx = self.pool.begin().await?;
+            sqlx::Executor::execute(&mut *tx, migration.up).await?;
+            sqlx::query("INSERT INTO schema_migrations (version, name) VALUES ($1, $2)")
+                .bind(migration.version)
+                .bind(migration.name)
+                .execute(&mut *tx)
+                .await?;
+            tx.commit().await?;
+        }
+
+        info!("Database schema is up to date");
+        Ok(())
+    }
+
+    /// Roll back the last `n` applied migrations in descending version
+    /// order, running each migration's `down` script inside its own
+    /// transaction and removing its tracking row.
+    pub async fn rollback(&self, n: usize) -> Result<()> {
+        self.ensure_migrations_table().await?;
+
+        let mut applied: Vec<(i32, String)> =
+            sqlx::query_as("SELECT version, name FROM schema_migrations ORDER BY version DESC LIMIT $1")
+                .bind(n as i64)
+                .fetch_all(&self.pool)
+                .await?;
+
+        for (version, name) in applied.drain(..) {
+            let migration = MIGRATIONS
+                .iter()
+                .find(|m| m.version == version)
+                .ok_or_else(|| StatsError::Other(format!("no embedded migration for applied version {}", version)))?;
+
+            info!("Rolling back migration {:04}_{}", version, name);
+
+            let mut tx = self.pool.begin().await?;
+            sqlx::Executor::execute(&mut *tx, migration.down).await?;
+            sqlx::query("DELETE FROM schema_migrations WHERE version = $1")
+                .bind(version)
+                .execute(&mut *tx)
+                .await?;
+            tx.commit().await?;
+        }
+
+        Ok(())
+    }
+
+    /// Report which embedded migrations are applied versus pending.
+    pub async fn migration_status(&self) -> Result<MigrationStatus> {
+        self.ensure_migrations_table().await?;
+
+        let applied_rows: Vec<(i32, String, String)> = sqlx::query_as(
+            "SELECT version, name, applied_at::text FROM schema_migrations ORDER BY version",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let applied: Vec<AppliedMigration> = applied_rows
+            .into_iter()
+            .map(|(version, name, applied_at)| AppliedMigration { version, name, applied_at })
+            .collect();
+
+        let applied_versions: std::collections::HashSet<i32> =
+            applied.iter().map(|m| m.version).collect();
+
+        let pending = MIGRATIONS
+            .iter()
+            .filter(|m| !applied_versions.contains(&m.version))
+            .map(|m| (m.version, m.name.to_string()))
+            .collect();
+
+        Ok(MigrationStatus { applied, pending })
+    }
+
+    /// Create the `*_history` tables and `BEFORE UPDATE OR DELETE` triggers
+    /// that copy the old row into them, so balance edits to weapon data
+    /// stay traceable without a separate ETL layer. Idempotent.
+    pub async fn enable_audit_logging(&self) -> Result<()> {
+        info!("Enabling audit logging on config_dropoffs, configurations, weapon_ammo_stats");
+
+        let statements = [
             r#"
-            CREATE TABLE IF NOT EXISTS barrels (
-                barrel_id SERIAL PRIMARY KEY,
-                barrel_name VARCHAR(100) NOT NULL UNIQUE
+            CREATE TABLE IF NOT EXISTS config_dropoffs_history (
+                history_id BIGSERIAL PRIMARY KEY,
+                config_id INTEGER NOT NULL,
+                range SMALLINT NOT NULL,
+                damage DECIMAL(5,1) NOT NULL,
+                operation VARCHAR(10) NOT NULL,
+                changed_at TIMESTAMPTZ NOT NULL DEFAULT now()
             )
             "#,
-            
-            // Ammo types table
             r#"
-            CREATE TABLE IF NOT EXISTS ammo_types (
-                ammo_id SERIAL PRIMARY KEY,
-                ammo_type_name VARCHAR(100) NOT NULL UNIQUE
+            CREATE TABLE IF NOT EXISTS configurations_history (
+                history_id BIGSERIAL PRIMARY KEY,
+                config_id INTEGER NOT NULL,
+                weapon_id INTEGER NOT NULL,
+                barrel_id INTEGER NOT NULL,
+                ammo_id INTEGER NOT NULL,
+                velocity SMALLINT NOT NULL,
+                rpm_single SMALLINT,
+                rpm_burst SMALLINT,
+                rpm_auto SMALLINT,
+                operation VARCHAR(10) NOT NULL,
+                changed_at TIMESTAMPTZ NOT NULL DEFAULT now()
             )
             "#,
-            
-            // Weapon ammo compatibility and stats
             r#"
-            CREATE TABLE IF NOT EXISTS weapon_ammo_stats (
-                weapon_id INTEGER NOT NULL REFERENCES weapons(weapon_id),
-                ammo_id INTEGER NOT NULL REFERENCES ammo_types(ammo_id),
+            CREATE TABLE IF NOT EXISTS weapon_ammo_stats_history (
+                history_id BIGSERIAL PRIMARY KEY,
+                weapon_id INTEGER NOT NULL,
+                ammo_id INTEGER NOT NULL,
                 magazine_size SMALLINT NOT NULL,
                 empty_reload_time DECIMAL(4,2),
                 tactical_reload_time DECIMAL(4,2),
                 headshot_multiplier DECIMAL(3,1) NOT NULL,
-                pellet_count SMALLINT DEFAULT 1,
-                PRIMARY KEY (weapon_id, ammo_id)
+                pellet_count SMALLINT,
+                operation VARCHAR(10) NOT NULL,
+                changed_at TIMESTAMPTZ NOT NULL DEFAULT now()
             )
             "#,
-            
-            // Configurations table
             r#"
-            CREATE TABLE IF NOT EXISTS configurations (
-                config_id SERIAL PRIMARY KEY,
-                weapon_id INTEGER NOT NULL REFERENCES weapons(weapon_id),
-                barrel_id INTEGER NOT NULL REFERENCES barrels(barrel_id),
-                ammo_id INTEGER NOT NULL REFERENCES ammo_types(ammo_id),
-                velocity SMALLINT NOT NULL,
-                rpm_single SMALLINT,
-                rpm_burst SMALLINT,
-                rpm_auto SMALLINT,
-                UNIQUE(weapon_id, barrel_id, ammo_id)
-            )
+            CREATE OR REPLACE FUNCTION log_config_dropoffs_history() RETURNS TRIGGER AS $$
+            BEGIN
+                INSERT INTO config_dropoffs_history (config_id, range, damage, operation)
+                VALUES (OLD.config_id, OLD.range, OLD.damage, TG_OP);
+                IF TG_OP = 'DELETE' THEN
+                    RETURN OLD;
+                END IF;
+                RETURN NEW;
+            END;
+            $$ LANGUAGE plpgsql
             "#,
-            
-            // Damage dropoff data
             r#"
-            CREATE TABLE IF NOT EXISTS config_dropoffs (
-                config_id INTEGER NOT NULL REFERENCES configurations(config_id),
-                range SMALLINT NOT NULL,
-                damage DECIMAL(5,1) NOT NULL,
-                PRIMARY KEY (config_id, range)
-            )
+            CREATE OR REPLACE FUNCTION log_configurations_history() RETURNS TRIGGER AS $$
+            BEGIN
+                INSERT INTO configurations_history
+                    (config_id, weapon_id, barrel_id, ammo_id, velocity, rpm_single, rpm_burst, rpm_auto, operation)
+                VALUES
+                    (OLD.config_id, OLD.weapon_id, OLD.barrel_id, OLD.ammo_id, OLD.velocity, OLD.rpm_single, OLD.rpm_burst, OLD.rpm_auto, TG_OP);
+                IF TG_OP = 'DELETE' THEN
+                    RETURN OLD;
+                END IF;
+                RETURN NEW;
+            END;
+            $$ LANGUAGE plpgsql
+            "#,
+            r#"
+            CREATE OR REPLACE FUNCTION log_weapon_ammo_stats_history() RETURNS TRIGGER AS $$
+            BEGIN
+                INSERT INTO weapon_ammo_stats_history
+                    (weapon_id, ammo_id, magazine_size, empty_reload_time, tactical_reload_time, headshot_multiplier, pellet_count, operation)
+                VALUES
+                    (OLD.weapon_id, OLD.ammo_id, OLD.magazine_size, OLD.empty_reload_time, OLD.tactical_reload_time, OLD.headshot_multiplier, OLD.pellet_count, TG_OP);
+                IF TG_OP = 'DELETE' THEN
+                    RETURN OLD;
+                END IF;
+                RETURN NEW;
+            END;
+            $$ LANGUAGE plpgsql
+            "#,
+            "DROP TRIGGER IF EXISTS trg_config_dropoffs_history ON config_dropoffs",
+            r#"
+            CREATE TRIGGER trg_config_dropoffs_history
+                BEFORE UPDATE OR DELETE ON config_dropoffs
+                FOR EACH ROW EXECUTE FUNCTION log_config_dropoffs_history()
+            "#,
+            "DROP TRIGGER IF EXISTS trg_configurations_history ON configurations",
+            r#"
+            CREATE TRIGGER trg_configurations_history
+                BEFORE UPDATE OR DELETE ON configurations
+                FOR EACH ROW EXECUTE FUNCTION log_configurations_history()
+            "#,
+            "DROP TRIGGER IF EXISTS trg_weapon_ammo_stats_history ON weapon_ammo_stats",
+            r#"
+            CREATE TRIGGER trg_weapon_ammo_stats_history
+                BEFORE UPDATE OR DELETE ON weapon_ammo_stats
+                FOR EACH ROW EXECUTE FUNCTION log_weapon_ammo_stats_history()
             "#,
         ];
 
-        // Execute schema creation statements
-        for statement in &schema_statements {
+        for statement in &statements {
             sqlx::query(statement).execute(&self.pool).await?;
         }
 
-        // Create indexes
-        let index_statements = [
-            "CREATE INDEX IF NOT EXISTS idx_weapons_category ON weapons(category_id)",
-            "CREATE INDEX IF NOT EXISTS idx_configurations_weapon ON configurations(weapon_id)",
-            "CREATE INDEX IF NOT EXISTS idx_config_dropoffs_config ON config_dropoffs(config_id)",
-            "CREATE INDEX IF NOT EXISTS idx_config_dropoffs_range ON config_dropoffs(range)",
-            "CREATE INDEX IF NOT EXISTS idx_weapon_ammo_stats_weapon ON weapon_ammo_stats(weapon_id)",
+        info!("Audit logging enabled");
+        Ok(())
+    }
+
+    /// Drop the audit triggers (history tables and their data are kept).
+    pub async fn disable_audit_logging(&self) -> Result<()> {
+        info!("Disabling audit logging");
+
+        let statements = [
+            "DROP TRIGGER IF EXISTS trg_config_dropoffs_history ON config_dropoffs",
+            "DROP TRIGGER IF EXISTS trg_configurations_history ON configurations",
+            "DROP TRIGGER IF EXISTS trg_weapon_ammo_stats_history ON weapon_ammo_stats",
+            "DROP FUNCTION IF EXISTS log_config_dropoffs_history()",
+            "DROP FUNCTION IF EXISTS log_configurations_history()",
+            "DROP FUNCTION IF EXISTS log_weapon_ammo_stats_history()",
         ];
 
-        for statement in &index_statements {
+        for statement in &statements {
             sqlx::query(statement).execute(&self.pool).await?;
         }
 
-        info!("Database schema created successfully");
+        info!("Audit logging disabled");
         Ok(())
     }
 
+    /// Fetch the prior damage curves recorded for a configuration's
+    /// dropoff table, ordered oldest to newest.
+    pub async fn history_for_config(&self, config_id: i32) -> Result<Vec<DropoffHistoryEntry>> {
+        let rows: Vec<(i32, i16, f64, String, String)> = sqlx::query_as(
+            "SELECT config_id, range, damage::float8, operation, changed_at::text
+             FROM config_dropoffs_history
+             WHERE config_id = $1
+             ORDER BY changed_at ASC",
+        )
+        .bind(config_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter()
+            .map(|(config_id, range, damage, operation, changed_at)| {
+                let operation = match operation.as_str() {
+                    "UPDATE" => HistoryOperation::Update,
+                    "DELETE" => HistoryOperation::Delete,
+                    other => {
+                        return Err(StatsError::Other(format!(
+                            "unrecognized history operation '{}'",
+                            other
+                        )))
+                    }
+                };
+                Ok(DropoffHistoryEntry { config_id, range, damage, operation, changed_at })
+            })
+            .collect()
+    }
+
+    /// Create the `config_effective_damage` and `config_ttk` views so
+    /// damage/TTK math is computed server-side instead of re-implemented by
+    /// every consumer.
+    ///
+    /// Invariant: both views assume the per-`config_id` rows in
+    /// `config_dropoffs` are meant to be read in ascending `range` order —
+    /// callers bucketing an arbitrary distance to "the nearest dropoff
+    /// range at or below it" must sort by `range` themselves, since SQL
+    /// does not guarantee row order without an `ORDER BY`. Rows where
+    /// `damage = 0` are excluded from both views, since they would make
+    /// `shots_to_kill` divide by zero.
+    pub async fn create_analytics_views(&self) -> Result<()> {
+        info!("Creating analytics views");
+
+        sqlx::query(
+            r#"
+            CREATE OR REPLACE VIEW config_effective_damage AS
+            SELECT
+                c.config_id,
+                c.weapon_id,
+                c.barrel_id,
+                c.ammo_id,
+                cd.range,
+                cd.damage::float8 AS damage_per_pellet,
+                (cd.damage * COALESCE(was.pellet_count, 1))::float8 AS damage_total,
+                (cd.damage * was.headshot_multiplier)::float8 AS headshot_damage_per_pellet,
+                (cd.damage * was.headshot_multiplier * COALESCE(was.pellet_count, 1))::float8 AS headshot_damage_total
+            FROM config_dropoffs cd
+            JOIN configurations c ON c.config_id = cd.config_id
+            JOIN weapon_ammo_stats was ON was.weapon_id = c.weapon_id AND was.ammo_id = c.ammo_id
+            WHERE cd.damage > 0
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            CREATE OR REPLACE VIEW config_ttk AS
+            SELECT
+                ed.config_id,
+                ed.weapon_id,
+                ed.barrel_id,
+                ed.ammo_id,
+                ed.range,
+                ed.damage_total,
+                CEIL(100.0 / ed.damage_total)::float8 AS shots_to_kill,
+                ((CEIL(100.0 / ed.damage_total) - 1) * 60000.0 / rpm.effective_rpm)::float8 AS ttk_ms
+            FROM config_effective_damage ed
+            JOIN (
+                SELECT config_id, COALESCE(rpm_auto, rpm_burst, rpm_single) AS effective_rpm
+                FROM configurations
+            ) rpm ON rpm.config_id = ed.config_id
+            WHERE rpm.effective_rpm IS NOT NULL AND ed.damage_total > 0
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        info!("Analytics views created successfully");
+        Ok(())
+    }
+
+    /// Stream `config_effective_damage` rows, optionally filtered to a
+    /// single configuration. Exposed publicly through `StatsClient`.
+    pub fn stream_effective_damage<'a>(
+        &'a self,
+        config_id: Option<i32>,
+    ) -> futures_util::stream::BoxStream<'a, Result<EffectiveDamageRow>> {
+        use futures_util::{StreamExt, TryStreamExt};
+
+        let stream = match config_id {
+            Some(id) => sqlx::query_as::<_, EffectiveDamageRow>(
+                "SELECT * FROM config_effective_damage WHERE config_id = $1 ORDER BY range",
+            )
+            .bind(id)
+            .fetch(&self.pool),
+            None => sqlx::query_as::<_, EffectiveDamageRow>(
+                "SELECT * FROM config_effective_damage ORDER BY config_id, range",
+            )
+            .fetch(&self.pool),
+        };
+
+        stream.map_err(StatsError::from).boxed()
+    }
+
+    /// Stream `config_ttk` rows, optionally filtered to a single
+    /// configuration.
+    pub fn stream_ttk<'a>(
+        &'a self,
+        config_id: Option<i32>,
+    ) -> futures_util::stream::BoxStream<'a, Result<TimeToKillRow>> {
+        use futures_util::{StreamExt, TryStreamExt};
+
+        let stream = match config_id {
+            Some(id) => sqlx::query_as::<_, TimeToKillRow>(
+                "SELECT * FROM config_ttk WHERE config_id = $1 ORDER BY range",
+            )
+            .bind(id)
+            .fetch(&self.pool),
+            None => sqlx::query_as::<_, TimeToKillRow>("SELECT * FROM config_ttk ORDER BY config_id, range")
+                .fetch(&self.pool),
+        };
+
+        stream.map_err(StatsError::from).boxed()
+    }
+
     /// Populate database from embedded weapons data
     pub async fn populate_from_embedded_data(&self) -> Result<()> {
         info!("Populating database from embedded weapons data");
@@ -355,6 +875,286 @@ impl DatabaseManager {
         Ok(())
     }
 
+    /// Reconcile the database with the given weapons JSON: upsert every
+    /// table on its natural key so changed stats overwrite existing rows,
+    /// and prune `weapon_ammo_stats`, `configurations` and `config_dropoffs`
+    /// rows whose natural key is no longer present in the incoming data.
+    /// Categories, weapons, barrels and ammo types are never deleted.
+    /// Runs in a single transaction.
+    pub async fn sync_from_json(&self, json_content: &str) -> Result<SyncSummary> {
+        use crate::models::WeaponsData;
+        use std::collections::HashMap;
+
+        let weapons_data: WeaponsData =
+            serde_json::from_str(json_content).map_err(|e| StatsError::ParseError(e))?;
+
+        debug!(
+            "Parsed {} categories from JSON for sync",
+            weapons_data.categories.len()
+        );
+
+        let mut summary = SyncSummary::default();
+        let mut tx = self.pool.begin().await?;
+
+        // Upsert categories (no mutable columns beyond the natural key itself)
+        for category in &weapons_data.categories {
+            let result = sqlx::query(
+                "INSERT INTO categories (category_name) VALUES ($1) ON CONFLICT (category_name) DO NOTHING"
+            )
+            .bind(&category.name)
+            .execute(&mut *tx)
+            .await?;
+            summary.categories.inserted += result.rows_affected() as i64;
+        }
+
+        let category_id_map: HashMap<String, i32> = sqlx::query_as::<_, (i32, String)>(
+            "SELECT category_id, category_name FROM categories",
+        )
+        .fetch_all(&mut *tx)
+        .await?
+        .into_iter()
+        .map(|(id, name)| (name, id))
+        .collect();
+
+        // Upsert weapons; category_id can change if a weapon moved categories
+        let mut weapon_id_map: HashMap<String, i32> = HashMap::new();
+        for category in &weapons_data.categories {
+            let Some(&category_id) = category_id_map.get(&category.name) else {
+                continue;
+            };
+
+            for weapon in &category.weapons {
+                let (weapon_id, inserted): (i32, bool) = sqlx::query_as(
+                    "INSERT INTO weapons (weapon_name, category_id) VALUES ($1, $2)
+                     ON CONFLICT (weapon_name) DO UPDATE SET category_id = EXCLUDED.category_id
+                     RETURNING weapon_id, (xmax = 0) AS inserted",
+                )
+                .bind(&weapon.name)
+                .bind(category_id)
+                .fetch_one(&mut *tx)
+                .await?;
+
+                if inserted {
+                    summary.weapons.inserted += 1;
+                } else {
+                    summary.weapons.updated += 1;
+                }
+                weapon_id_map.insert(weapon.name.clone(), weapon_id);
+            }
+        }
+
+        // Upsert barrels and ammo types (no mutable columns beyond the natural key)
+        let mut barrel_names: Vec<String> = Vec::new();
+        let mut ammo_names: Vec<String> = Vec::new();
+        for category in &weapons_data.categories {
+            for weapon in &category.weapons {
+                for stat in &weapon.stats {
+                    barrel_names.push(stat.barrel_type.clone());
+                    ammo_names.push(stat.ammo_type.clone());
+                }
+                for ammo_name in weapon.ammo_stats.keys() {
+                    ammo_names.push(ammo_name.clone());
+                }
+            }
+        }
+        barrel_names.sort();
+        barrel_names.dedup();
+        ammo_names.sort();
+        ammo_names.dedup();
+
+        for barrel_name in &barrel_names {
+            let result = sqlx::query(
+                "INSERT INTO barrels (barrel_name) VALUES ($1) ON CONFLICT (barrel_name) DO NOTHING",
+            )
+            .bind(barrel_name)
+            .execute(&mut *tx)
+            .await?;
+            summary.barrels.inserted += result.rows_affected() as i64;
+        }
+
+        for ammo_name in &ammo_names {
+            let result = sqlx::query(
+                "INSERT INTO ammo_types (ammo_type_name) VALUES ($1) ON CONFLICT (ammo_type_name) DO NOTHING",
+            )
+            .bind(ammo_name)
+            .execute(&mut *tx)
+            .await?;
+            summary.ammo_types.inserted += result.rows_affected() as i64;
+        }
+
+        let barrel_id_map: HashMap<String, i32> = sqlx::query_as::<_, (i32, String)>(
+            "SELECT barrel_id, barrel_name FROM barrels",
+        )
+        .fetch_all(&mut *tx)
+        .await?
+        .into_iter()
+        .map(|(id, name)| (name, id))
+        .collect();
+
+        let ammo_id_map: HashMap<String, i32> = sqlx::query_as::<_, (i32, String)>(
+            "SELECT ammo_id, ammo_type_name FROM ammo_types",
+        )
+        .fetch_all(&mut *tx)
+        .await?
+        .into_iter()
+        .map(|(id, name)| (name, id))
+        .collect();
+
+        // Upsert weapon_ammo_stats, tracking natural keys still present in the JSON
+        let mut live_weapon_ammo_keys: Vec<(i32, i32)> = Vec::new();
+        for category in &weapons_data.categories {
+            for weapon in &category.weapons {
+                let Some(&weapon_id) = weapon_id_map.get(&weapon.name) else {
+                    continue;
+                };
+
+                for (ammo_name, ammo_stat) in &weapon.ammo_stats {
+                    let Some(&ammo_id) = ammo_id_map.get(ammo_name) else {
+                        continue;
+                    };
+
+                    let (inserted,): (bool,) = sqlx::query_as(
+                        "INSERT INTO weapon_ammo_stats
+                            (weapon_id, ammo_id, magazine_size, empty_reload_time, tactical_reload_time, headshot_multiplier, pellet_count)
+                         VALUES ($1, $2, $3, $4, $5, $6, $7)
+                         ON CONFLICT (weapon_id, ammo_id) DO UPDATE SET
+                            magazine_size = EXCLUDED.magazine_size,
+                            empty_reload_time = EXCLUDED.empty_reload_time,
+                            tactical_reload_time = EXCLUDED.tactical_reload_time,
+                            headshot_multiplier = EXCLUDED.headshot_multiplier,
+                            pellet_count = EXCLUDED.pellet_count
+                         RETURNING (xmax = 0) AS inserted",
+                    )
+                    .bind(weapon_id)
+                    .bind(ammo_id)
+                    .bind(ammo_stat.mag_size)
+                    .bind(ammo_stat.empty_reload)
+                    .bind(ammo_stat.tactical_reload)
+                    .bind(ammo_stat.headshot_multiplier)
+                    .bind(ammo_stat.pellet_count)
+                    .fetch_one(&mut *tx)
+                    .await?;
+
+                    if inserted {
+                        summary.weapon_ammo_stats.inserted += 1;
+                    } else {
+                        summary.weapon_ammo_stats.updated += 1;
+                    }
+                    live_weapon_ammo_keys.push((weapon_id, ammo_id));
+                }
+            }
+        }
+
+        summary.weapon_ammo_stats.deleted = prune_missing(
+            &mut tx,
+            "weapon_ammo_stats",
+            "weapon_id",
+            "ammo_id",
+            &live_weapon_ammo_keys,
+        )
+        .await?;
+
+        // Upsert configurations and their dropoffs, tracking natural keys still present
+        let mut live_config_keys: Vec<(i32, i32, i32)> = Vec::new();
+        let mut live_dropoff_keys: Vec<(i32, i32)> = Vec::new();
+        for category in &weapons_data.categories {
+            for weapon in &category.weapons {
+                let Some(&weapon_id) = weapon_id_map.get(&weapon.name) else {
+                    continue;
+                };
+
+                for stat in &weapon.stats {
+                    let (Some(&barrel_id), Some(&ammo_id)) = (
+                        barrel_id_map.get(&stat.barrel_type),
+                        ammo_id_map.get(&stat.ammo_type),
+                    ) else {
+                        continue;
+                    };
+
+                    let (config_id, inserted): (i32, bool) = sqlx::query_as(
+                        "INSERT INTO configurations
+                            (weapon_id, barrel_id, ammo_id, velocity, rpm_single, rpm_burst, rpm_auto)
+                         VALUES ($1, $2, $3, $4, $5, $6, $7)
+                         ON CONFLICT (weapon_id, barrel_id, ammo_id) DO UPDATE SET
+                            velocity = EXCLUDED.velocity,
+                            rpm_single = EXCLUDED.rpm_single,
+                            rpm_burst = EXCLUDED.rpm_burst,
+                            rpm_auto = EXCLUDED.rpm_auto
+                         RETURNING config_id, (xmax = 0) AS inserted",
+                    )
+                    .bind(weapon_id)
+                    .bind(barrel_id)
+                    .bind(ammo_id)
+                    .bind(stat.velocity)
+                    .bind(stat.rpm_single)
+                    .bind(stat.rpm_burst)
+                    .bind(stat.rpm_auto)
+                    .fetch_one(&mut *tx)
+                    .await?;
+
+                    if inserted {
+                        summary.configurations.inserted += 1;
+                    } else {
+                        summary.configurations.updated += 1;
+                    }
+                    live_config_keys.push((weapon_id, barrel_id, ammo_id));
+
+                    for dropoff in &stat.dropoffs {
+                        let (inserted,): (bool,) = sqlx::query_as(
+                            "INSERT INTO config_dropoffs (config_id, range, damage) VALUES ($1, $2, $3)
+                             ON CONFLICT (config_id, range) DO UPDATE SET damage = EXCLUDED.damage
+                             RETURNING (xmax = 0) AS inserted",
+                        )
+                        .bind(config_id)
+                        .bind(dropoff.range)
+                        .bind(dropoff.damage)
+                        .fetch_one(&mut *tx)
+                        .await?;
+
+                        if inserted {
+                            summary.config_dropoffs.inserted += 1;
+                        } else {
+                            summary.config_dropoffs.updated += 1;
+                        }
+                        live_dropoff_keys.push((config_id, dropoff.range as i32));
+                    }
+                }
+            }
+        }
+
+        // Prune child rows before parents: config_dropoffs.config_id has a
+        // non-deferrable FK to configurations(config_id), so a configuration
+        // pruned first would still have live dropoff rows pointing at it.
+        summary.config_dropoffs.deleted =
+            prune_missing(&mut tx, "config_dropoffs", "config_id", "range", &live_dropoff_keys).await?;
+
+        summary.configurations.deleted = prune_missing_triple(
+            &mut tx,
+            "configurations",
+            "weapon_id",
+            "barrel_id",
+            "ammo_id",
+            &live_config_keys,
+        )
+        .await?;
+
+        tx.commit().await?;
+
+        info!(
+            "Synced from JSON: weapons +{}/~{}, configurations +{}/~{}/-{}, dropoffs +{}/~{}/-{}",
+            summary.weapons.inserted,
+            summary.weapons.updated,
+            summary.configurations.inserted,
+            summary.configurations.updated,
+            summary.configurations.deleted,
+            summary.config_dropoffs.inserted,
+            summary.config_dropoffs.updated,
+            summary.config_dropoffs.deleted,
+        );
+
+        Ok(summary)
+    }
+
     /// Reset database - Drop and recreate all tables
     pub async fn reset_database(&self) -> Result<()> {
         info!("Resetting database (drop and recreate schema)");
@@ -362,12 +1162,16 @@ impl DatabaseManager {
         // Drop all tables and sequences in correct order (reverse dependency order)
         let drop_statements = [
             "DROP TABLE IF EXISTS config_dropoffs CASCADE",
-            "DROP TABLE IF EXISTS configurations CASCADE", 
+            "DROP TABLE IF EXISTS configurations CASCADE",
             "DROP TABLE IF EXISTS weapon_ammo_stats CASCADE",
             "DROP TABLE IF EXISTS weapons CASCADE",
             "DROP TABLE IF EXISTS ammo_types CASCADE",
             "DROP TABLE IF EXISTS barrels CASCADE",
             "DROP TABLE IF EXISTS categories CASCADE",
+            // Also drop the migration tracking table itself, otherwise the
+            // create_schema() call below sees version 1 already recorded
+            // as applied and skips it, leaving no data tables at all.
+            "DROP TABLE IF EXISTS schema_migrations CASCADE",
             // Drop sequences explicitly to avoid conflicts
             "DROP SEQUENCE IF EXISTS categories_category_id_seq CASCADE",
             "DROP SEQUENCE IF EXISTS weapons_weapon_id_seq CASCADE",
@@ -488,3 +1292,71 @@ impl DatabaseManager {
         Ok(report)
     }
 }
+
+/// Delete rows from `table` whose `(key_a, key_b)` pair is not among
+/// `live_keys`, returning the number of rows removed. Used by
+/// `sync_from_json` to prune rows whose natural key disappeared from the
+/// incoming JSON.
+async fn prune_missing(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    table: &str,
+    key_a: &str,
+    key_b: &str,
+    live_keys: &[(i32, i32)],
+) -> Result<i64> {
+    let (a_vals, b_vals): (Vec<i32>, Vec<i32>) = live_keys.iter().copied().unzip();
+
+    let sql = format!(
+        "DELETE FROM {table} t
+         WHERE NOT EXISTS (
+             SELECT 1 FROM unnest($1::int[], $2::int[]) AS k({key_a}, {key_b})
+             WHERE k.{key_a} = t.{key_a} AND k.{key_b} = t.{key_b}
+         )",
+        table = table,
+        key_a = key_a,
+        key_b = key_b,
+    );
+
+    let result = sqlx::query(&sql).bind(a_vals).bind(b_vals).execute(&mut **tx).await?;
+    Ok(result.rows_affected() as i64)
+}
+
+/// Three-column variant of `prune_missing`, used for `configurations`
+/// whose natural key is `(weapon_id, barrel_id, ammo_id)`.
+async fn prune_missing_triple(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    table: &str,
+    key_a: &str,
+    key_b: &str,
+    key_c: &str,
+    live_keys: &[(i32, i32, i32)],
+) -> Result<i64> {
+    let mut a_vals = Vec::with_capacity(live_keys.len());
+    let mut b_vals = Vec::with_capacity(live_keys.len());
+    let mut c_vals = Vec::with_capacity(live_keys.len());
+    for &(a, b, c) in live_keys {
+        a_vals.push(a);
+        b_vals.push(b);
+        c_vals.push(c);
+    }
+
+    let sql = format!(
+        "DELETE FROM {table} t
+         WHERE NOT EXISTS (
+             SELECT 1 FROM unnest($1::int[], $2::int[], $3::int[]) AS k({key_a}, {key_b}, {key_c})
+             WHERE k.{key_a} = t.{key_a} AND k.{key_b} = t.{key_b} AND k.{key_c} = t.{key_c}
+         )",
+        table = table,
+        key_a = key_a,
+        key_b = key_b,
+        key_c = key_c,
+    );
+
+    let result = sqlx::query(&sql)
+        .bind(a_vals)
+        .bind(b_vals)
+        .bind(c_vals)
+        .execute(&mut **tx)
+        .await?;
+    Ok(result.rows_affected() as i64)
+}
@@ -0,0 +1,101 @@
+//! Data models: database configuration and the JSON shapes parsed from
+//! `weapons.json`.
+
+use crate::database::manager::ConnectionOptions;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// Configuration for connecting to the PostgreSQL backend.
+#[derive(Debug, Clone, Default)]
+pub struct DatabaseConfig {
+    url: String,
+    pub max_connections: u32,
+    /// Per-connection session tuning applied via `after_connect` on every
+    /// connection the pool opens. Defaults to no tuning, so existing
+    /// callers that don't set this are unaffected.
+    pub connection_options: ConnectionOptions,
+}
+
+impl DatabaseConfig {
+    /// Create a configuration pointing at `url` with the given connection pool size.
+    pub fn new(url: impl Into<String>, max_connections: u32) -> Self {
+        Self { url: url.into(), max_connections, connection_options: ConnectionOptions::default() }
+    }
+
+    /// The PostgreSQL connection string.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+}
+
+/// Result of `DatabaseManager::validate_data`.
+#[derive(Debug, Clone)]
+pub struct ValidationReport {
+    pub is_valid: bool,
+    pub issues: Vec<String>,
+    pub table_counts: HashMap<String, i64>,
+}
+
+/// Top-level shape of `weapons.json`.
+#[derive(Debug, Deserialize)]
+pub struct WeaponsData {
+    pub categories: Vec<Category>,
+}
+
+/// A weapon category (e.g. "Assault Rifles") and its weapons.
+#[derive(Debug, Deserialize)]
+pub struct Category {
+    pub name: String,
+    pub weapons: Vec<Weapon>,
+}
+
+/// A single weapon: its barrel/ammo configurations and per-ammo-type stats.
+#[derive(Debug, Deserialize)]
+pub struct Weapon {
+    pub name: String,
+    pub stats: Vec<Configuration>,
+    pub ammo_stats: HashMap<String, WeaponAmmoStats>,
+}
+
+/// One barrel/ammo combination for a weapon, with its damage dropoff curve.
+#[derive(Debug, Deserialize)]
+pub struct Configuration {
+    pub barrel_type: String,
+    pub ammo_type: String,
+    pub velocity: i16,
+    pub rpm_single: Option<i16>,
+    pub rpm_burst: Option<i16>,
+    pub rpm_auto: Option<i16>,
+    pub dropoffs: Vec<ConfigDropoff>,
+}
+
+/// Damage at a given range for a configuration.
+#[derive(Debug, Deserialize)]
+pub struct ConfigDropoff {
+    pub range: i16,
+    pub damage: f64,
+}
+
+/// Magazine/reload/headshot stats for a weapon with a given ammo type.
+#[derive(Debug, Deserialize)]
+pub struct WeaponAmmoStats {
+    pub mag_size: i16,
+    pub empty_reload: Option<f64>,
+    pub tactical_reload: Option<f64>,
+    pub headshot_multiplier: f64,
+    pub pellet_count: i16,
+}
+
+/// A barrel attachment, as stored in the `barrels` table.
+#[derive(Debug, Clone)]
+pub struct Barrel {
+    pub barrel_id: i32,
+    pub barrel_name: String,
+}
+
+/// An ammo type, as stored in the `ammo_types` table.
+#[derive(Debug, Clone)]
+pub struct AmmoType {
+    pub ammo_id: i32,
+    pub ammo_type_name: String,
+}